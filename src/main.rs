@@ -3,7 +3,7 @@ use rand::Rng;
 use std::env;
 use std::fs::{self, File};
 use std::io;
-use std::io::Write;
+use std::io::{Read, Seek, SeekFrom, Write};
 use std::path::Path;
 use std::process::Command;
 
@@ -11,7 +11,10 @@ fn main() -> std::io::Result<()> {
     // Get the directory and playback duration from command-line arguments
     let args: Vec<String> = env::args().collect();
     if args.len() < 3 {
-        eprintln!("Usage: {} <video_directory> <clip_duration>", args[0]);
+        eprintln!(
+            "Usage: {} <video_directory> <clip_duration> [--clips-per-video N] [--inline] [--to-end] [--total-duration SECONDS]",
+            args[0]
+        );
         return Ok(());
     }
 
@@ -20,6 +23,30 @@ fn main() -> std::io::Result<()> {
         .parse()
         .expect("Second argument must be a positive integer representing clip duration in seconds");
 
+    let clips_per_video: u64 = parse_flag_value(&args[3..], "--clips-per-video")
+        .map(|value| {
+            value
+                .parse()
+                .expect("--clips-per-video must be a positive integer")
+        })
+        .unwrap_or(1);
+
+    // Hand mpv an edl:// URI directly instead of writing /tmp/playlist.edl.
+    let inline = args[3..].iter().any(|arg| arg == "--inline");
+
+    // Each video gets one segment from a random start (up to `clip_duration`)
+    // to the end of the file, so no duration probe is needed.
+    let to_end = args[3..].iter().any(|arg| arg == "--to-end");
+
+    // Ignores --clips-per-video/--to-end; appends clip_duration-long segments
+    // from reshuffled sources until the target runtime is reached.
+    let total_duration: Option<u64> =
+        parse_flag_value(&args[3..], "--total-duration").map(|value| {
+            value
+                .parse()
+                .expect("--total-duration must be a positive integer")
+        });
+
     let video_extensions = vec!["mp4", "mkv", "avi", "mov"]; // Add more as needed
 
     // Get a list of all video files in the directory
@@ -49,52 +76,85 @@ fn main() -> std::io::Result<()> {
     videos.shuffle(&mut rng);
 
     println!("Found {} videos in {}.", videos.len(), video_dir);
-    println!(
-        "Generating EDL file with {} seconds for each clip...",
-        clip_duration
-    );
-
-    // Create an EDL file
-    let edl_path = "/tmp/playlist.edl";
-    let mut edl_file = std::fs::OpenOptions::new()
-        .write(true)
-        .create(true)
-        .truncate(true) // Ensure the file is emptied
-        .open(edl_path)?;
-
-    writeln!(edl_file, "# mpv EDL v0")?;
-
-    for video in &videos {
-        // Get video duration
-        let duration_secs = match get_video_duration(video) {
-            Ok(secs) => secs,
-            Err(e) => {
-                println!("Could not get video duration for video: {:#?} {e}", video);
-                15 // Default to 15 seconds if unavailable
-            }
-        };
+    let plan = if let Some(target) = total_duration {
+        format!("{}-second clips filling {} seconds", clip_duration, target)
+    } else if to_end {
+        "one clip per video from a random start to the end of the file".to_string()
+    } else {
+        format!("{} {}-second clip(s) per video", clips_per_video, clip_duration)
+    };
+    println!("Generating EDL with {}...", plan);
 
-        // Pick a random start time, clamping to ensure a valid segment
-        let max_start_time = duration_secs.saturating_sub(clip_duration);
-        let start_time = rng.gen_range(0..=max_start_time);
+    let segments: Vec<Segment> = if let Some(target) = total_duration {
+        build_target_duration_segments(&videos, clip_duration, target, &mut rng)
+    } else if to_end {
+        videos
+            .iter()
+            .map(|video| Segment {
+                path: video.as_path(),
+                start: Some(rng.gen_range(0..=clip_duration)),
+                length: None,
+            })
+            .collect()
+    } else {
+        // Pick `clips_per_video` non-overlapping segments per video, keeping
+        // each video's segments grouped together so they can be interleaved
+        // below.
+        let mut segments_per_video: Vec<Vec<Segment>> = Vec::with_capacity(videos.len());
+        for video in &videos {
+            let duration_secs = duration_or_default(video);
 
-        println!("Adding {:?}: start={}s", video, start_time);
+            let starts =
+                pick_segment_starts(duration_secs, clip_duration, clips_per_video, &mut rng);
+            segments_per_video.push(
+                starts
+                    .into_iter()
+                    .map(|start| Segment {
+                        path: video.as_path(),
+                        start: Some(start),
+                        length: Some(clip_duration),
+                    })
+                    .collect(),
+            );
+        }
 
-        // Write to the EDL file
-        writeln!(
-            edl_file,
-            "{},{},{}",
-            path_to_edl(video.to_string_lossy().as_ref()),
-            start_time,
-            clip_duration
-        )?;
-    }
+        interleave_without_repeats(segments_per_video)
+    };
+
+    let entries: Vec<String> = segments
+        .iter()
+        .map(|segment| {
+            println!(
+                "Adding {:?}: start={:?} length={:?}",
+                segment.path, segment.start, segment.length
+            );
+            format_segment(segment)
+        })
+        .collect();
+
+    let mpv_target = if inline {
+        build_inline_edl_uri(&entries)
+    } else {
+        let edl_path = "/tmp/playlist.edl";
+        let mut edl_file = std::fs::OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true) // Ensure the file is emptied
+            .open(edl_path)?;
+
+        writeln!(edl_file, "# mpv EDL v0")?;
+        for entry in &entries {
+            writeln!(edl_file, "{}", entry)?;
+        }
+
+        println!("EDL file generated: {}", edl_path);
+        edl_path.to_string()
+    };
 
-    println!("EDL file generated: {}", edl_path);
     println!("Starting playback...");
 
-    // Launch mpv with the EDL file
-    let status = std::process::Command::new("mpv").arg(edl_path).status();
+    // Launch mpv with the EDL file or inline edl:// URI
+    let status = std::process::Command::new("mpv").arg(&mpv_target).status();
 
     if let Err(err) = status {
         eprintln!("Error starting playback: {}", err);
@@ -109,7 +169,319 @@ fn path_to_edl(path: &str) -> String {
     format!("%{}%{}", path.len(), path)
 }
 
+/// One `path[,start=N][,length=N]` entry in an mpv EDL v0 playlist. `start`
+/// and `length` are optional per the named-parameter syntax: an omitted
+/// `start` defaults to 0 and an omitted `length` plays to the end of the
+/// source, which is how `--to-end` segments skip specifying a length at all.
+#[derive(Clone, Copy)]
+struct Segment<'a> {
+    path: &'a Path,
+    start: Option<u64>,
+    length: Option<u64>,
+}
+
+/// Formats a [`Segment`] using the named-parameter EDL syntax, e.g.
+/// `%6%f1.mkv,start=10,length=20`, omitting any field that isn't set.
+fn format_segment(segment: &Segment) -> String {
+    let mut parts = vec![path_to_edl(segment.path.to_string_lossy().as_ref())];
+    if let Some(start) = segment.start {
+        parts.push(format!("start={}", start));
+    }
+    if let Some(length) = segment.length {
+        parts.push(format!("length={}", length));
+    }
+    parts.join(",")
+}
+
+/// Builds an `edl://` URI mpv can be launched with directly. Inline EDLs use
+/// the same entry syntax as the file-based format but separate entries with
+/// `;` instead of newlines, and have no `# mpv EDL v0` header.
+fn build_inline_edl_uri(entries: &[String]) -> String {
+    format!("edl://{}", entries.join(";"))
+}
+
+/// Looks for `--flag value` in `args` and returns `value` if present.
+fn parse_flag_value<'a>(args: &'a [String], flag: &str) -> Option<&'a str> {
+    args.iter()
+        .position(|arg| arg == flag)
+        .and_then(|i| args.get(i + 1))
+        .map(String::as_str)
+}
+
+/// Picks `count` non-overlapping start times for `clip_duration`-long
+/// segments in a `duration_secs`-long source by dividing it into `count`
+/// equal slots and picking a random start within each.
+fn pick_segment_starts(
+    duration_secs: u64,
+    clip_duration: u64,
+    count: u64,
+    rng: &mut impl Rng,
+) -> Vec<u64> {
+    if count == 0 || clip_duration == 0 {
+        return Vec::new();
+    }
+
+    // Not enough room for every requested segment; fall back to as many as
+    // fit (at least one), packed back to back from the start of the file.
+    if clip_duration.saturating_mul(count) > duration_secs {
+        let max_fit = (duration_secs / clip_duration).max(1);
+        return (0..max_fit).map(|i| i * clip_duration).collect();
+    }
+
+    let slot_width = duration_secs / count;
+    (0..count)
+        .map(|i| {
+            let slot_start = i * slot_width;
+            let max_start = slot_start + slot_width - clip_duration;
+            rng.gen_range(slot_start..=max_start)
+        })
+        .collect()
+}
+
+/// Flattens per-video segment queues into one ordering that avoids
+/// back-to-back segments from the same source, falling back to a repeat
+/// only when no other video has segments left.
+fn interleave_without_repeats(segments_per_video: Vec<Vec<Segment>>) -> Vec<Segment> {
+    use std::collections::BinaryHeap;
+
+    // Reverse each queue so `pop()` yields segments in their original order.
+    let mut queues: Vec<Vec<Segment>> = segments_per_video
+        .into_iter()
+        .map(|mut q| {
+            q.reverse();
+            q
+        })
+        .collect();
+
+    let mut heap: BinaryHeap<(usize, usize)> = queues
+        .iter()
+        .enumerate()
+        .filter(|(_, q)| !q.is_empty())
+        .map(|(idx, q)| (q.len(), idx))
+        .collect();
+
+    let mut result = Vec::new();
+    let mut last_idx: Option<usize> = None;
+
+    while let Some((count, idx)) = heap.pop() {
+        let (count, idx) = if Some(idx) == last_idx {
+            match heap.pop() {
+                Some(alternative) => {
+                    heap.push((count, idx)); // put the skipped entry back for later
+                    alternative
+                }
+                // No other source has segments left; a repeat is unavoidable.
+                None => (count, idx),
+            }
+        } else {
+            (count, idx)
+        };
+
+        if let Some(segment) = queues[idx].pop() {
+            result.push(segment);
+            last_idx = Some(idx);
+            if count > 1 {
+                heap.push((count - 1, idx));
+            }
+        }
+    }
+
+    result
+}
+
+/// Keeps appending random `clip_duration`-long segments from `videos`,
+/// reshuffling and looping back over the sources once exhausted, until the
+/// accumulated segment length reaches `target`. The final segment is
+/// clamped so the total lands exactly on `target`, and sources are reused
+/// across loops since the EDL format allows referencing a file more than
+/// once.
+fn build_target_duration_segments<'a>(
+    videos: &'a [std::path::PathBuf],
+    clip_duration: u64,
+    target: u64,
+    rng: &mut impl Rng,
+) -> Vec<Segment<'a>> {
+    if clip_duration == 0 || videos.is_empty() {
+        return Vec::new();
+    }
+
+    let mut segments = Vec::new();
+    let mut queue: Vec<&Path> = Vec::new();
+    let mut total = 0u64;
+    let mut last_video: Option<&Path> = None;
+    // Sources get requeued across loops, so cache each one's duration rather
+    // than re-probing (and re-spawning ffprobe) every time it's reused.
+    let mut durations: std::collections::HashMap<&Path, u64> = std::collections::HashMap::new();
+
+    while total < target {
+        if queue.is_empty() {
+            queue = videos.iter().map(std::path::PathBuf::as_path).collect();
+            queue.shuffle(rng);
+            // Don't let the video we just placed land first in the new
+            // queue too, or it plays back-to-back across the reshuffle
+            // boundary (the repeat interleave_without_repeats avoids for
+            // --clips-per-video).
+            if queue.len() > 1 && Some(queue[0]) == last_video {
+                let swap_with = rng.gen_range(1..queue.len());
+                queue.swap(0, swap_with);
+            }
+        }
+        let video = queue.remove(0);
+        last_video = Some(video);
+
+        let duration_secs = *durations
+            .entry(video)
+            .or_insert_with(|| duration_or_default(video));
+        let start = pick_segment_starts(duration_secs, clip_duration, 1, rng)
+            .into_iter()
+            .next()
+            .unwrap_or(0);
+
+        let length = clip_duration.min(target - total);
+        total += length;
+
+        segments.push(Segment {
+            path: video,
+            start: Some(start),
+            length: Some(length),
+        });
+    }
+
+    segments
+}
+
+/// Looks up a video's duration, falling back to a default of 15 seconds (and
+/// logging why) when it can't be determined.
+fn duration_or_default(path: &Path) -> u64 {
+    match get_video_duration(path) {
+        Ok(secs) => secs,
+        Err(e) => {
+            println!("Could not get video duration for video: {:#?} {e}", path);
+            15
+        }
+    }
+}
+
 fn get_video_duration(path: &Path) -> Result<u64, io::Error> {
+    // mp4/mov containers carry their duration in the moov/mvhd box, so we can
+    // read it directly without shelling out to ffprobe. Anything else (mkv,
+    // avi, or an mp4 missing the box) falls back to the old ffprobe path.
+    let extension = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+
+    if extension == "mp4" || extension == "mov" {
+        if let Some(secs) = read_mvhd_duration(path) {
+            return Ok(secs);
+        }
+    }
+
+    get_video_duration_ffprobe(path)
+}
+
+/// Walks the top-level ISO-BMFF boxes looking for `moov/mvhd` and computes
+/// `duration / timescale` in seconds. Returns `None` (rather than erroring)
+/// if the file is truncated or missing the box, so callers can fall back to
+/// ffprobe instead of failing outright.
+fn read_mvhd_duration(path: &Path) -> Option<u64> {
+    let mut file = File::open(path).ok()?;
+    let len = file_len(&mut file)?;
+    let moov = find_box(&mut file, "moov", len)?;
+    let mvhd = find_box(&mut file, "mvhd", moov.end)?;
+
+    file.seek(SeekFrom::Start(mvhd.start)).ok()?;
+    let mut version = [0u8; 1];
+    file.read_exact(&mut version).ok()?;
+    file.seek(SeekFrom::Current(3)).ok()?; // flags
+
+    let (timescale, duration) = if version[0] == 1 {
+        file.seek(SeekFrom::Current(8 + 8)).ok()?; // creation/modification time (u64 each)
+        let timescale = read_u32(&mut file)?;
+        let duration = read_u64(&mut file)?;
+        (timescale, duration)
+    } else {
+        file.seek(SeekFrom::Current(4 + 4)).ok()?; // creation/modification time (u32 each)
+        let timescale = read_u32(&mut file)?;
+        let duration = read_u32(&mut file)? as u64;
+        (timescale, duration)
+    };
+
+    if timescale == 0 {
+        return None;
+    }
+
+    Some(duration / timescale as u64)
+}
+
+struct BoxLoc {
+    start: u64,
+    end: u64,
+}
+
+/// Scans sibling boxes up to `limit`, starting at the beginning of the file
+/// when looking for `moov` and at the current position otherwise (just
+/// inside `moov` when looking for `mvhd`), and returns the location of the
+/// first box matching `name`. Handles the ISO-BMFF 64-bit "largesize" case
+/// (`size == 1`, with the real size in the 8 bytes following the box type),
+/// which is common for large `mdat` atoms and would otherwise make the next
+/// box's header get read from the wrong offset.
+fn find_box(file: &mut File, name: &str, limit: u64) -> Option<BoxLoc> {
+    let mut pos = if name == "moov" {
+        0
+    } else {
+        file.stream_position().ok()?
+    };
+    file.seek(SeekFrom::Start(pos)).ok()?;
+
+    while pos + 8 <= limit {
+        let raw_size = read_u32(file)? as u64;
+        let mut box_type = [0u8; 4];
+        file.read_exact(&mut box_type).ok()?;
+        let box_type = std::str::from_utf8(&box_type).ok()?;
+
+        let (header_len, size) = if raw_size == 1 {
+            (16u64, read_u64(file)?)
+        } else {
+            (8u64, raw_size)
+        };
+
+        if size < header_len {
+            return None;
+        }
+
+        if box_type == name {
+            return Some(BoxLoc {
+                start: pos + header_len,
+                end: pos + size,
+            });
+        }
+
+        pos += size;
+        file.seek(SeekFrom::Start(pos)).ok()?;
+    }
+
+    None
+}
+
+fn file_len(file: &mut File) -> Option<u64> {
+    file.seek(SeekFrom::End(0)).ok()
+}
+
+fn read_u32(file: &mut File) -> Option<u32> {
+    let mut buf = [0u8; 4];
+    file.read_exact(&mut buf).ok()?;
+    Some(u32::from_be_bytes(buf))
+}
+
+fn read_u64(file: &mut File) -> Option<u64> {
+    let mut buf = [0u8; 8];
+    file.read_exact(&mut buf).ok()?;
+    Some(u64::from_be_bytes(buf))
+}
+
+fn get_video_duration_ffprobe(path: &Path) -> Result<u64, io::Error> {
     let output = Command::new("ffprobe")
         .arg("-v")
         .arg("error")
@@ -152,3 +524,295 @@ fn get_video_duration(path: &Path) -> Result<u64, io::Error> {
             )
         })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pick_segment_starts_falls_back_when_not_enough_room() {
+        // clip_duration * count (20) exceeds duration_secs (15), so only one
+        // segment fits and it starts at the front of the file.
+        let mut rng = rand::thread_rng();
+        let starts = pick_segment_starts(15, 10, 2, &mut rng);
+        assert_eq!(starts, vec![0]);
+    }
+
+    #[test]
+    fn pick_segment_starts_spaces_segments_without_overlap() {
+        let mut rng = rand::thread_rng();
+        let clip_duration = 10;
+        let starts = pick_segment_starts(100, clip_duration, 5, &mut rng);
+
+        assert_eq!(starts.len(), 5);
+        for window in starts.windows(2) {
+            assert!(
+                window[1] >= window[0] + clip_duration,
+                "segments starting at {} and {} overlap",
+                window[0],
+                window[1]
+            );
+        }
+        assert!(*starts.last().unwrap() + clip_duration <= 100);
+    }
+
+    #[test]
+    fn interleave_without_repeats_forces_only_the_unavoidable_tail_repeat() {
+        let a = Path::new("a.mkv");
+        let b = Path::new("b.mkv");
+        let segs_a: Vec<Segment> = (0..3)
+            .map(|i| Segment {
+                path: a,
+                start: Some(i * 10),
+                length: Some(10),
+            })
+            .collect();
+        let segs_b = vec![Segment {
+            path: b,
+            start: Some(0),
+            length: Some(10),
+        }];
+
+        let result = interleave_without_repeats(vec![segs_a, segs_b]);
+
+        assert_eq!(result.len(), 4);
+        // With 3 segments from `a` and 1 from `b`, `a` can't avoid
+        // back-to-back placement for its last two segments.
+        assert_eq!(result[2].path, a);
+        assert_eq!(result[3].path, a);
+        // But the first repeat of `a` is kept apart from the others.
+        assert_ne!(result[0].path, result[1].path);
+    }
+
+    #[test]
+    fn build_target_duration_segments_clamps_final_segment_to_target() {
+        // Fixture paths don't exist on disk, so duration lookups fall back
+        // to the default and every draw is deterministic length-wise.
+        let videos = vec![
+            std::path::PathBuf::from("chunk0-5-fixture-a.mp4"),
+            std::path::PathBuf::from("chunk0-5-fixture-b.mp4"),
+        ];
+        let mut rng = rand::thread_rng();
+
+        let segments = build_target_duration_segments(&videos, 10, 25, &mut rng);
+
+        let total: u64 = segments.iter().map(|s| s.length.unwrap()).sum();
+        assert_eq!(total, 25);
+        // 25 isn't a multiple of the 10-second clip length, so the last
+        // segment must be shortened rather than overshooting the target.
+        assert_eq!(segments.last().unwrap().length, Some(5));
+    }
+
+    #[test]
+    fn build_target_duration_segments_requeues_sources_once_exhausted() {
+        let videos = vec![
+            std::path::PathBuf::from("chunk0-5-fixture-c.mp4"),
+            std::path::PathBuf::from("chunk0-5-fixture-d.mp4"),
+        ];
+        let mut rng = rand::thread_rng();
+
+        // Target requires 3 segments from only 2 sources, so the queue must
+        // be reshuffled and at least one source reused.
+        let segments = build_target_duration_segments(&videos, 10, 30, &mut rng);
+
+        assert_eq!(segments.len(), 3);
+        assert!(segments.len() > videos.len());
+
+        let mut counts = std::collections::HashMap::new();
+        for segment in &segments {
+            *counts.entry(segment.path).or_insert(0u32) += 1;
+        }
+        assert!(
+            counts.values().any(|&count| count > 1),
+            "expected at least one source to be reused, got {:?}",
+            counts
+        );
+    }
+
+    #[test]
+    fn build_target_duration_segments_never_repeats_a_source_across_a_reshuffle() {
+        let videos = vec![
+            std::path::PathBuf::from("chunk0-5-fixture-e.mp4"),
+            std::path::PathBuf::from("chunk0-5-fixture-f.mp4"),
+        ];
+        let mut rng = rand::thread_rng();
+
+        // Enough segments to force several reshuffles of the 2-video queue.
+        let segments = build_target_duration_segments(&videos, 10, 120, &mut rng);
+
+        for window in segments.windows(2) {
+            assert_ne!(
+                window[0].path, window[1].path,
+                "same source played back-to-back across a reshuffle boundary"
+            );
+        }
+    }
+
+    #[test]
+    fn interleave_without_repeats_single_video_has_no_choice_but_to_repeat() {
+        let a = Path::new("a.mkv");
+        let segs_a = vec![
+            Segment {
+                path: a,
+                start: Some(0),
+                length: Some(10),
+            },
+            Segment {
+                path: a,
+                start: Some(10),
+                length: Some(10),
+            },
+        ];
+
+        let result = interleave_without_repeats(vec![segs_a.clone()]);
+
+        assert_eq!(result.len(), 2);
+        assert_eq!(result[0].start, segs_a[0].start);
+        assert_eq!(result[1].start, segs_a[1].start);
+    }
+
+    #[test]
+    fn format_segment_renders_every_field_combination() {
+        let path = Path::new("f1.mkv");
+
+        assert_eq!(
+            format_segment(&Segment {
+                path,
+                start: None,
+                length: None,
+            }),
+            "%6%f1.mkv"
+        );
+        assert_eq!(
+            format_segment(&Segment {
+                path,
+                start: Some(10),
+                length: None,
+            }),
+            "%6%f1.mkv,start=10"
+        );
+        assert_eq!(
+            format_segment(&Segment {
+                path,
+                start: None,
+                length: Some(20),
+            }),
+            "%6%f1.mkv,length=20"
+        );
+        assert_eq!(
+            format_segment(&Segment {
+                path,
+                start: Some(10),
+                length: Some(20),
+            }),
+            "%6%f1.mkv,start=10,length=20"
+        );
+    }
+
+    #[test]
+    fn build_inline_edl_uri_joins_entries_with_semicolons() {
+        let entries = vec![
+            "%6%f1.mkv,start=10,length=20".to_string(),
+            "%6%f2.mkv".to_string(),
+        ];
+        assert_eq!(
+            build_inline_edl_uri(&entries),
+            "edl://%6%f1.mkv,start=10,length=20;%6%f2.mkv"
+        );
+    }
+
+    fn wrap_box(box_type: &str, body: &[u8]) -> Vec<u8> {
+        let mut b = Vec::new();
+        b.extend_from_slice(&((8 + body.len()) as u32).to_be_bytes());
+        b.extend_from_slice(box_type.as_bytes());
+        b.extend_from_slice(body);
+        b
+    }
+
+    fn mvhd_body(version: u8, timescale: u32, duration: u64) -> Vec<u8> {
+        let mut body = vec![version, 0, 0, 0]; // version + flags
+        if version == 1 {
+            body.extend_from_slice(&0u64.to_be_bytes()); // creation_time
+            body.extend_from_slice(&0u64.to_be_bytes()); // modification_time
+            body.extend_from_slice(&timescale.to_be_bytes());
+            body.extend_from_slice(&duration.to_be_bytes());
+        } else {
+            body.extend_from_slice(&0u32.to_be_bytes()); // creation_time
+            body.extend_from_slice(&0u32.to_be_bytes()); // modification_time
+            body.extend_from_slice(&timescale.to_be_bytes());
+            body.extend_from_slice(&(duration as u32).to_be_bytes());
+        }
+        body
+    }
+
+    /// Like `wrap_box`, but with a 64-bit "largesize" header (`size == 1`,
+    /// real size in the 8 bytes after the box type) instead of the normal
+    /// 32-bit size field.
+    fn wrap_largesize_box(box_type: &str, body: &[u8]) -> Vec<u8> {
+        let mut b = Vec::new();
+        b.extend_from_slice(&1u32.to_be_bytes());
+        b.extend_from_slice(box_type.as_bytes());
+        b.extend_from_slice(&((16 + body.len()) as u64).to_be_bytes());
+        b.extend_from_slice(body);
+        b
+    }
+
+    /// Writes `bytes` to a uniquely-named file under the OS temp dir so
+    /// `read_mvhd_duration`, which takes a `&Path`, has something to open.
+    fn write_fixture(name: &str, bytes: &[u8]) -> std::path::PathBuf {
+        let path = std::env::temp_dir().join(format!(
+            "mpv-mixtape-test-{}-{}-{:?}.mp4",
+            name,
+            std::process::id(),
+            std::thread::current().id()
+        ));
+        fs::write(&path, bytes).expect("write test fixture");
+        path
+    }
+
+    #[test]
+    fn reads_version0_mvhd_duration() {
+        let moov = wrap_box("moov", &wrap_box("mvhd", &mvhd_body(0, 1000, 5000)));
+        let path = write_fixture("v0", &moov);
+        assert_eq!(read_mvhd_duration(&path), Some(5));
+        fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn reads_version1_mvhd_duration() {
+        let moov = wrap_box("moov", &wrap_box("mvhd", &mvhd_body(1, 1000, 9000)));
+        let path = write_fixture("v1", &moov);
+        assert_eq!(read_mvhd_duration(&path), Some(9));
+        fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn returns_none_when_mvhd_box_missing() {
+        // `moov` present, but its only child is `trak`, not `mvhd`.
+        let moov = wrap_box("moov", &wrap_box("trak", &[0u8; 4]));
+        let path = write_fixture("no-mvhd", &moov);
+        assert_eq!(read_mvhd_duration(&path), None);
+        fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn skips_largesize_box_before_moov() {
+        // A largesize `free` box sitting before `moov` must be skipped using
+        // its real (64-bit) size, not the `size == 1` placeholder.
+        let free = wrap_largesize_box("free", &[0u8; 4]);
+        let moov = wrap_box("moov", &wrap_box("mvhd", &mvhd_body(0, 1000, 5000)));
+        let path = write_fixture("largesize", &[free, moov].concat());
+        assert_eq!(read_mvhd_duration(&path), Some(5));
+        fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn returns_none_for_truncated_file() {
+        let mvhd = wrap_box("mvhd", &mvhd_body(0, 1000, 5000));
+        let mut moov = wrap_box("moov", &mvhd);
+        moov.truncate(moov.len() - 4); // chop off mvhd's 4-byte duration field
+        let path = write_fixture("truncated", &moov);
+        assert_eq!(read_mvhd_duration(&path), None);
+        fs::remove_file(&path).ok();
+    }
+}